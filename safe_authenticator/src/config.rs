@@ -0,0 +1,325 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Authenticator configuration storage.
+//!
+//! The authenticator's mutable state - registered apps, the revocation
+//! queue, the access-control policy, delegation links, the reauth token
+//! secret and issued token hashes - lives in a single serialised `Config` blob, kept in a
+//! well-known entry of the user's config-root `MData` and guarded by the
+//! entry's version for optimistic-concurrency updates. Every other module
+//! reads and writes that blob through the helpers below rather than
+//! touching the entry directly.
+
+use super::{AuthError, AuthFuture};
+use futures::Future;
+use maidsafe_utilities::serialisation::{deserialise, serialise};
+use policy::Policy;
+use routing::ClientError;
+use safe_core::{Client, CoreError, FutureExt};
+use safe_core::ipc::req::AppExchangeInfo;
+use safe_core::ipc::resp::AppKeys;
+use safe_core::utils::generate_random_vector;
+use std::collections::{BTreeSet, HashMap};
+use tiny_keccak::sha3_256;
+
+/// Length in bytes of the server-side secret mixed into every reauth token,
+/// so the token can't be recomputed from public/derivable fields alone.
+const TOKEN_SECRET_LEN: usize = 32;
+
+const CONFIG_ENTRY_KEY: &[u8] = b"authenticator-config";
+
+/// A registered app: its exchange info, its dedicated key pair, the
+/// containers it has been granted (used by `app_auth::app_state` to detect
+/// partial revocation), and a generation counter bumped every time a
+/// reauth token is issued for it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppInfo {
+    /// The app's exchange info, as supplied in the auth request.
+    pub info: AppExchangeInfo,
+    /// The app's dedicated key pair.
+    pub keys: AppKeys,
+    /// Containers granted to this app; not reduced when some are revoked.
+    pub containers: BTreeSet<String>,
+    /// Bumped on every reauth token issuance; invalidates older tokens.
+    pub token_generation: u64,
+}
+
+impl AppInfo {
+    /// Construct a fresh `AppInfo` for a never-seen-before app.
+    pub fn new(info: AppExchangeInfo, keys: AppKeys) -> Self {
+        AppInfo {
+            info: info,
+            keys: keys,
+            containers: Default::default(),
+            token_generation: 0,
+        }
+    }
+}
+
+/// Apps registered with the authenticator, keyed by the sha3-256 hash of their app id.
+pub type Apps = HashMap<[u8; 32], AppInfo>;
+
+/// The blob actually stored on the network.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct Config {
+    apps: Apps,
+    revocation_queue: BTreeSet<String>,
+    policy: Option<Policy>,
+    /// Grantor app id -> the apps it has delegated containers to, so that
+    /// revoking a grantor can cascade to the entries it produced.
+    delegations: HashMap<String, BTreeSet<String>>,
+    /// Server-side secret mixed into every reauth token; never sent to apps.
+    token_secret: Option<Vec<u8>>,
+    /// Token hash (sha3-256 of the token itself) -> the app id and token
+    /// generation it was issued for. Looked up by `reauthenticate`, cleared
+    /// on every revocation (partial or full) so a revoked app's outstanding
+    /// token can never be replayed.
+    reauth_tokens: HashMap<Vec<u8>, (String, u64)>,
+}
+
+fn config_dir(client: &Client<()>) -> Box<AuthFuture<::safe_core::MDataInfo>> {
+    client.config_root_dir().map_err(AuthError::from).into_box()
+}
+
+fn read_config(client: &Client<()>) -> Box<AuthFuture<(u64, Config)>> {
+    let c2 = client.clone();
+
+    config_dir(client)
+        .and_then(move |dir| {
+            c2.get_mdata_value(dir.name, dir.type_tag, CONFIG_ENTRY_KEY.to_vec())
+                .map_err(AuthError::from)
+                .into_box()
+        })
+        .then(|res| match res {
+            Ok(value) => {
+                let config = deserialise(&value.content)?;
+                Ok((value.entry_version, config))
+            }
+            Err(AuthError::CoreError(
+                CoreError::RoutingClientError(ClientError::NoSuchEntry),
+            )) => Ok((0, Config::default())),
+            Err(e) => Err(e),
+        })
+        .into_box()
+}
+
+fn write_config(client: &Client<()>, config: &Config, version: u64) -> Box<AuthFuture<()>> {
+    let c2 = client.clone();
+    let encoded = fry!(serialise(config).map_err(AuthError::from));
+
+    config_dir(client)
+        .and_then(move |dir| {
+            c2.mutate_mdata_entries(dir.name, dir.type_tag, CONFIG_ENTRY_KEY.to_vec(), encoded, version + 1)
+                .map_err(AuthError::from)
+                .into_box()
+        })
+        .into_box()
+}
+
+/// Update the config via a read-modify-write cycle at the current version.
+fn update_config<F>(client: &Client<()>, f: F) -> Box<AuthFuture<()>>
+where
+    F: FnOnce(&mut Config) + 'static,
+{
+    update_config_and_return(client, f)
+}
+
+/// Like `update_config`, but also returns a value computed from the updated
+/// config - e.g. the app's new token generation after bumping it.
+fn update_config_and_return<F, T>(client: &Client<()>, f: F) -> Box<AuthFuture<T>>
+where
+    F: FnOnce(&mut Config) -> T + 'static,
+    T: 'static,
+{
+    let c2 = client.clone();
+
+    read_config(client)
+        .and_then(move |(version, mut config)| {
+            let result = f(&mut config);
+            write_config(&c2, &config, version).map(move |_| result)
+        })
+        .into_box()
+}
+
+/// List every app currently registered with the authenticator.
+pub fn list_apps(client: &Client<()>) -> Box<AuthFuture<(u64, Apps)>> {
+    read_config(client)
+        .map(|(version, config)| (version, config.apps))
+        .into_box()
+}
+
+/// Register (or overwrite) an app's entry in the config.
+pub fn insert_app(client: &Client<()>, app: AppInfo) -> Box<AuthFuture<()>> {
+    update_config(client, move |config| {
+        let app_id_hash = sha3_256(app.info.id.as_bytes());
+        let _ = config.apps.insert(app_id_hash, app);
+    })
+}
+
+/// Return the app ids currently pending revocation.
+pub fn get_revocation_queue(client: &Client<()>) -> Box<AuthFuture<Option<(u64, BTreeSet<String>)>>> {
+    read_config(client)
+        .map(|(version, config)| if config.revocation_queue.is_empty() {
+            None
+        } else {
+            Some((version, config.revocation_queue))
+        })
+        .into_box()
+}
+
+/// Load the declarative access-control policy, if one has been configured.
+pub fn get_policy(client: &Client<()>) -> Box<AuthFuture<Option<(u64, Policy)>>> {
+    read_config(client)
+        .map(|(version, config)| config.policy.map(|policy| (version, policy)))
+        .into_box()
+}
+
+/// Record that `grantee_app_id` was granted capabilities delegated from
+/// `grantor_app_id`, so that revoking the grantor can cascade to the
+/// delegated entries it produced.
+pub fn link_delegation(
+    client: &Client<()>,
+    grantor_app_id: String,
+    grantee_app_id: String,
+) -> Box<AuthFuture<()>> {
+    update_config(client, move |config| {
+        let _ = config
+            .delegations
+            .entry(grantor_app_id)
+            .or_insert_with(BTreeSet::new)
+            .insert(grantee_app_id);
+    })
+}
+
+/// Every app that was granted containers delegated from `grantor_app_id`.
+pub fn get_delegates(client: &Client<()>, grantor_app_id: String) -> Box<AuthFuture<BTreeSet<String>>> {
+    read_config(client)
+        .map(move |(_version, config)| {
+            config
+                .delegations
+                .get(&grantor_app_id)
+                .cloned()
+                .unwrap_or_default()
+        })
+        .into_box()
+}
+
+/// Queue `app_id` for full revocation, so `check_revocation` rejects any
+/// further authentication attempt for it until it is explicitly
+/// re-authenticated.
+pub fn enqueue_revocation(client: &Client<()>, app_id: String) -> Box<AuthFuture<()>> {
+    update_config(client, move |config| {
+        let _ = config.revocation_queue.insert(app_id);
+    })
+}
+
+/// Record that `containers` have been granted to `app_id_hash`'s app, on top
+/// of whatever it already holds. This is the set `app_auth::app_state`
+/// diffs the access-container entry against to detect partial revocation, so
+/// it must be updated every time a grant widens what an app holds. The set
+/// is only ever extended: neither a revocation nor a later grant removes
+/// containers from it.
+pub fn insert_app_containers(
+    client: &Client<()>,
+    app_id_hash: [u8; 32],
+    containers: BTreeSet<String>,
+) -> Box<AuthFuture<()>> {
+    update_config(client, move |config| {
+        if let Some(app) = config.apps.get_mut(&app_id_hash) {
+            app.containers.extend(containers);
+        }
+    })
+}
+
+/// Return the server-side secret mixed into every reauth token (generating
+/// and persisting one on first use) and bump `app_id_hash`'s token
+/// generation counter, in the same read-modify-write cycle.
+///
+/// These two mutations used to be two independent `update_config_and_return`
+/// calls joined together; since each reads the current version and writes at
+/// `version + 1` unaware of the other, that raced on every successful
+/// authentication. Folding them into one cycle makes the pair atomic.
+/// Every previously issued token for this app carries the old generation and
+/// is rejected by `reauthenticate` from this point on.
+pub fn issue_token_secret_and_generation(
+    client: &Client<()>,
+    app_id_hash: [u8; 32],
+) -> Box<AuthFuture<(Vec<u8>, u64)>> {
+    update_config_and_return(client, move |config| {
+        let secret = match config.token_secret {
+            Some(ref secret) => secret.clone(),
+            None => {
+                let secret = generate_random_vector(TOKEN_SECRET_LEN);
+                config.token_secret = Some(secret.clone());
+                secret
+            }
+        };
+
+        let generation = match config.apps.get_mut(&app_id_hash) {
+            Some(app) => {
+                app.token_generation += 1;
+                app.token_generation
+            }
+            None => 0,
+        };
+
+        (secret, generation)
+    })
+}
+
+/// Persist the hash of a freshly issued reauth token, replacing any token
+/// previously stored for the same app.
+pub fn store_reauth_token(
+    client: &Client<()>,
+    _app_id_hash: [u8; 32],
+    token_hash: Vec<u8>,
+    app_id: String,
+    generation: u64,
+) -> Box<AuthFuture<()>> {
+    update_config(client, move |config| {
+        config
+            .reauth_tokens
+            .retain(|_, &mut (ref existing_app_id, _)| *existing_app_id != app_id);
+        let _ = config.reauth_tokens.insert(token_hash, (app_id, generation));
+    })
+}
+
+/// Look up the app id and generation a reauth token (by its hash) was issued
+/// for, if it hasn't since been cleared by a revocation.
+pub fn get_reauth_token(
+    client: &Client<()>,
+    token_hash: Vec<u8>,
+) -> Box<AuthFuture<Option<(String, u64)>>> {
+    read_config(client)
+        .map(move |(_version, config)| config.reauth_tokens.get(&token_hash).cloned())
+        .into_box()
+}
+
+/// Delete any reauth token stored for `app_id_hash`'s app, so it can't be
+/// replayed after a revocation (partial or full).
+pub fn clear_reauth_token(client: &Client<()>, app_id_hash: [u8; 32]) -> Box<AuthFuture<()>> {
+    update_config(client, move |config| {
+        let app_id = match config.apps.get(&app_id_hash) {
+            Some(app) => app.info.id.clone(),
+            None => return,
+        };
+        config
+            .reauth_tokens
+            .retain(|_, &mut (ref existing_app_id, _)| *existing_app_id != app_id);
+    })
+}
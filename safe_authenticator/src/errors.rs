@@ -0,0 +1,79 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Authenticator error type.
+
+use futures::Future;
+use maidsafe_utilities::serialisation::SerialisationError;
+use safe_core::CoreError;
+use std::fmt;
+
+/// Future resolving to `T` or an `AuthError`, returned by every asynchronous
+/// authenticator routine.
+pub type AuthFuture<T> = Future<Item = T, Error = AuthError>;
+
+/// Authenticator error.
+#[derive(Debug)]
+pub enum AuthError {
+    /// Error originating from `safe_core`.
+    CoreError(CoreError),
+    /// Error (de)serialising a config / access-container blob.
+    Serialisation(SerialisationError),
+    /// The request was refused because the requesting app isn't entitled to
+    /// what it asked for - the configured policy denies it, a delegation
+    /// grantor doesn't hold what it's trying to hand on, or the grantor
+    /// isn't currently `Authenticated`.
+    OperationForbidden,
+    /// Catch-all error with a message, for conditions that don't warrant
+    /// their own variant.
+    Unexpected(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AuthError::CoreError(ref err) => write!(f, "Core error: {}", err),
+            AuthError::Serialisation(ref err) => write!(f, "Serialisation error: {}", err),
+            AuthError::OperationForbidden => write!(f, "Operation forbidden"),
+            AuthError::Unexpected(ref err) => write!(f, "Unexpected: {}", err),
+        }
+    }
+}
+
+impl From<CoreError> for AuthError {
+    fn from(err: CoreError) -> Self {
+        AuthError::CoreError(err)
+    }
+}
+
+impl From<SerialisationError> for AuthError {
+    fn from(err: SerialisationError) -> Self {
+        AuthError::Serialisation(err)
+    }
+}
+
+impl<'a> From<&'a str> for AuthError {
+    fn from(err: &'a str) -> Self {
+        AuthError::Unexpected(err.to_owned())
+    }
+}
+
+impl From<String> for AuthError {
+    fn from(err: String) -> Self {
+        AuthError::Unexpected(err)
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! Declarative access-control policy for container grants.
+//!
+//! A `Policy` is a table of `(subject, object, action, effect)` rules plus a
+//! grouping of app ids into roles, so a single rule can cover many apps at
+//! once. `subject` is either an app id or a role name, `object` is a
+//! container name (`_documents`, `apps/<app id>`, ...), and `action` is a
+//! `Permission`. A request is allowed if at least one matching `Allow` rule
+//! exists and no matching `Deny` rule overrides it.
+
+use config;
+use futures::Future;
+use safe_core::{Client, FutureExt};
+use safe_core::ipc::req::ffi::Permission;
+use std::collections::{BTreeSet, HashMap};
+
+use super::AuthFuture;
+
+/// The permissions an `insert_app_container` grant can cover. Kept in sync
+/// with the set of `Permission` variants so a policy can decide on each one.
+const ALL_PERMISSIONS: [Permission; 5] = [
+    Permission::Read,
+    Permission::Insert,
+    Permission::Update,
+    Permission::Delete,
+    Permission::ManagePermissions,
+];
+
+/// Whether a rule grants or forbids the action it matches.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    /// The rule grants the matched action.
+    Allow,
+    /// The rule forbids the matched action, overriding any `Allow`.
+    Deny,
+}
+
+/// A single policy row: "`subject` may/may not `action` on `object`".
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// App id or role name this rule applies to.
+    pub subject: String,
+    /// Container name this rule applies to.
+    pub object: String,
+    /// The permission being allowed or denied.
+    pub action: Permission,
+    /// Whether this rule grants or forbids `action`.
+    pub effect: Effect,
+}
+
+/// A loaded policy ruleset: the rule table plus the app-to-role grouping.
+///
+/// This is persisted verbatim in the authenticator config (see `config::get_policy`),
+/// so it must stay (de)serialisable.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Policy {
+    rules: Vec<PolicyRule>,
+    groups: HashMap<String, BTreeSet<String>>,
+}
+
+impl Policy {
+    /// Construct a policy from an explicit rule table and role groupings.
+    pub fn new(rules: Vec<PolicyRule>, groups: HashMap<String, BTreeSet<String>>) -> Self {
+        Policy { rules, groups }
+    }
+
+    /// Load the policy ruleset from the authenticator config.
+    ///
+    /// Returns `None` if no policy has been configured, so callers can fall
+    /// back to the legacy "grant whatever was asked" behaviour rather than
+    /// denying everything by default.
+    pub fn load(client: &Client<()>) -> Box<AuthFuture<Option<Policy>>> {
+        config::get_policy(client)
+            .map(|entry| entry.map(|(_version, policy)| policy))
+            .into_box()
+    }
+
+    /// Every subject name that could match `app_id`: the app id itself, plus
+    /// every role it has been grouped into.
+    fn subjects_for(&self, app_id: &str) -> BTreeSet<String> {
+        let mut subjects = btree_set![app_id.to_string()];
+        for (role, members) in &self.groups {
+            if members.contains(app_id) {
+                let _ = subjects.insert(role.clone());
+            }
+        }
+        subjects
+    }
+
+    /// Whether `app_id` may perform `action` on `object`, i.e. at least one
+    /// matching `Allow` rule exists and no matching `Deny` rule overrides it.
+    fn is_allowed(&self, app_id: &str, object: &str, action: Permission) -> bool {
+        let subjects = self.subjects_for(app_id);
+        let mut allowed = false;
+
+        for rule in &self.rules {
+            if rule.object != object || rule.action != action || !subjects.contains(&rule.subject) {
+                continue;
+            }
+            match rule.effect {
+                Effect::Deny => return false,
+                Effect::Allow => allowed = true,
+            }
+        }
+
+        allowed
+    }
+
+    /// The subset of `requested` this policy permits `app_id` to hold on
+    /// `container`.
+    pub fn filter_permissions(
+        &self,
+        app_id: &str,
+        container: &str,
+        requested: &BTreeSet<Permission>,
+    ) -> BTreeSet<Permission> {
+        requested
+            .iter()
+            .filter(|action| self.is_allowed(app_id, container, **action))
+            .cloned()
+            .collect()
+    }
+
+    /// Every permission this policy permits `app_id` to hold on `container`,
+    /// irrespective of what was requested.
+    pub fn permitted(&self, app_id: &str, container: &str) -> BTreeSet<Permission> {
+        ALL_PERMISSIONS
+            .iter()
+            .filter(|action| self.is_allowed(app_id, container, **action))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(subject: &str, object: &str, action: Permission, effect: Effect) -> PolicyRule {
+        PolicyRule {
+            subject: subject.to_owned(),
+            object: object.to_owned(),
+            action: action,
+            effect: effect,
+        }
+    }
+
+    #[test]
+    fn no_matching_rule_denies_by_default() {
+        let policy = Policy::new(vec![], HashMap::new());
+        assert!(!policy.is_allowed("media-app", "_pictures", Permission::Read));
+    }
+
+    #[test]
+    fn matching_allow_rule_permits() {
+        let policy = Policy::new(
+            vec![rule("media-app", "_pictures", Permission::Read, Effect::Allow)],
+            HashMap::new(),
+        );
+        assert!(policy.is_allowed("media-app", "_pictures", Permission::Read));
+        assert!(!policy.is_allowed("media-app", "_pictures", Permission::Insert));
+    }
+
+    #[test]
+    fn deny_overrides_allow_regardless_of_rule_order() {
+        let policy = Policy::new(
+            vec![
+                rule("media-app", "_pictures", Permission::Delete, Effect::Allow),
+                rule("media-app", "_pictures", Permission::Delete, Effect::Deny),
+            ],
+            HashMap::new(),
+        );
+        assert!(!policy.is_allowed("media-app", "_pictures", Permission::Delete));
+
+        let reversed = Policy::new(
+            vec![
+                rule("media-app", "_pictures", Permission::Delete, Effect::Deny),
+                rule("media-app", "_pictures", Permission::Delete, Effect::Allow),
+            ],
+            HashMap::new(),
+        );
+        assert!(!reversed.is_allowed("media-app", "_pictures", Permission::Delete));
+    }
+
+    #[test]
+    fn role_grouping_lets_one_rule_cover_many_apps() {
+        let mut groups = HashMap::new();
+        let _ = groups.insert(
+            "media".to_owned(),
+            btree_set!["app-one".to_owned(), "app-two".to_owned()],
+        );
+        let policy = Policy::new(
+            vec![rule("media", "_pictures", Permission::Read, Effect::Allow)],
+            groups,
+        );
+
+        assert!(policy.is_allowed("app-one", "_pictures", Permission::Read));
+        assert!(policy.is_allowed("app-two", "_pictures", Permission::Read));
+        assert!(!policy.is_allowed("app-three", "_pictures", Permission::Read));
+    }
+
+    #[test]
+    fn filter_permissions_keeps_only_what_is_allowed() {
+        let policy = Policy::new(
+            vec![rule("media-app", "_pictures", Permission::Read, Effect::Allow)],
+            HashMap::new(),
+        );
+        let requested = btree_set![Permission::Read, Permission::Delete];
+
+        assert_eq!(
+            policy.filter_permissions("media-app", "_pictures", &requested),
+            btree_set![Permission::Read]
+        );
+    }
+}
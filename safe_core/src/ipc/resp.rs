@@ -0,0 +1,89 @@
+// Copyright 2017 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under (1) the MaidSafe.net Commercial License,
+// version 1.0 or later, or (2) The General Public License (GPL), version 3, depending on which
+// licence you accepted on initial access to the Software (the "Licences").
+//
+// By contributing code to the SAFE Network Software, or to this project generally, you agree to be
+// bound by the terms of the MaidSafe Contributor Agreement.  This, along with the Licenses can be
+// found in the root directory of this project at LICENSE, COPYING and CONTRIBUTOR.
+//
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.
+//
+// Please review the Licences for the specific language governing permissions and limitations
+// relating to use of the SAFE Network Software.
+
+//! IPC response types handed back to an app once it clears authentication.
+
+use rust_sodium::crypto::{box_, sign};
+use {CoreError, MDataInfo};
+
+/// Dedicated key pair minted for an app on first authentication, used from
+/// then on to read/write its granted containers and to register with
+/// MaidManagers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AppKeys {
+    /// Owner signing key of the account the app was authenticated under.
+    pub owner_key: sign::PublicKey,
+    /// App's public signing key, registered with MaidManagers.
+    pub sign_pk: sign::PublicKey,
+    /// App's secret signing key.
+    pub sign_sk: sign::SecretKey,
+    /// App's public encryption key.
+    pub enc_key: box_::PublicKey,
+    /// App's secret encryption key.
+    pub enc_sk: box_::SecretKey,
+}
+
+impl AppKeys {
+    /// Generate a random key pair for an app owned by `owner_key`.
+    pub fn random(owner_key: sign::PublicKey) -> Self {
+        let (sign_pk, sign_sk) = sign::gen_keypair();
+        let (enc_key, enc_sk) = box_::gen_keypair();
+
+        AppKeys {
+            owner_key: owner_key,
+            sign_pk: sign_pk,
+            sign_sk: sign_sk,
+            enc_key: enc_key,
+            enc_sk: enc_sk,
+        }
+    }
+}
+
+/// Info an app needs to locate the user's access container entry.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccessContInfo {
+    /// Name of the access container's underlying `MutableData`.
+    pub id: ::routing::XorName,
+    /// Type tag of the access container's underlying `MutableData`.
+    pub tag: u64,
+}
+
+impl AccessContInfo {
+    /// Derive an `AccessContInfo` from the access container's `MDataInfo`.
+    pub fn from_mdata_info(dir: MDataInfo) -> Result<Self, CoreError> {
+        Ok(AccessContInfo {
+            id: dir.name,
+            tag: dir.type_tag,
+        })
+    }
+}
+
+/// Everything an app needs once it has been granted access to the user's
+/// account.
+#[derive(Clone, Debug)]
+pub struct AuthGranted {
+    /// The dedicated key pair the app should use from now on.
+    pub app_keys: AppKeys,
+    /// Serialised bootstrap config the app needs to connect to the network.
+    pub bootstrap_config: Vec<u8>,
+    /// Info the app needs to locate its access container entry.
+    pub access_container: AccessContInfo,
+    /// Opaque, revocable token the app can present to `reauthenticate` on a
+    /// later connection to skip the full authentication round-trip. See
+    /// `app_auth::issue_reauth_token`.
+    pub reauth_token: Vec<u8>,
+}